@@ -1,20 +1,132 @@
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs;
 
+// ======================
+// DIAGNOSTICS
+// ======================
+
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug)]
+enum CompileError {
+    UnexpectedChar(char, Span),
+    UnexpectedToken(String, Span),
+    ExpectedToken(String, Span),
+}
+
+impl CompileError {
+    fn span(&self) -> Span {
+        match self {
+            CompileError::UnexpectedChar(_, span) => *span,
+            CompileError::UnexpectedToken(_, span) => *span,
+            CompileError::ExpectedToken(_, span) => *span,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            CompileError::UnexpectedChar(c, _) => format!("unexpected character '{}'", c),
+            CompileError::UnexpectedToken(what, _) => format!("unexpected token, {}", what),
+            CompileError::ExpectedToken(what, _) => format!("expected {}", what),
+        }
+    }
+
+    fn undeclared_variable(name: &str, span: Span) -> Self {
+        CompileError::UnexpectedToken(format!("undeclared variable '{}'", name), span)
+    }
+
+    fn division_by_zero(span: Span) -> Self {
+        CompileError::UnexpectedToken("division by zero".to_string(), span)
+    }
+
+    fn arithmetic_overflow(span: Span) -> Self {
+        CompileError::UnexpectedToken("integer overflow".to_string(), span)
+    }
+
+    fn float_variable(name: &str, span: Span) -> Self {
+        CompileError::UnexpectedToken(
+            format!(
+                "cannot store a floating-point value in '{}' (the compiled backend only supports integer variables)",
+                name
+            ),
+            span,
+        )
+    }
+}
+
+fn report_error(source: &str, err: &CompileError) {
+    let span = err.span();
+    let chars: Vec<char> = source.chars().collect();
+
+    let mut line = 1;
+    let mut line_start = 0;
+
+    for (i, &c) in chars.iter().enumerate().take(span.start) {
+        if c == '\n' {
+            line += 1;
+            line_start = i + 1;
+        }
+    }
+
+    let column = span.start - line_start + 1;
+
+    let line_end = chars[line_start..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map(|p| line_start + p)
+        .unwrap_or(chars.len());
+
+    let line_text: String = chars[line_start..line_end].iter().collect();
+    let underline_len = span.end.saturating_sub(span.start).max(1);
+
+    eprintln!("error: {}", err.message());
+    eprintln!("{}:{}", line, column);
+
+    let prefix = format!("{} | ", line);
+    eprintln!("{}{}", prefix, line_text);
+    eprintln!(
+        "{}{}",
+        " ".repeat(prefix.len() + column - 1),
+        "^".repeat(underline_len)
+    );
+}
+
 // ======================
 // TOKEN
 // ======================
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 enum Token {
     Int(i64),
+    Float(f64),
+    Ident(String),
     Plus,
     Minus,
     Mul,
     Div,
+    Lt,
+    Gt,
+    Eq,
+    Neq,
+    Le,
+    Ge,
     LParen,
     RParen,
+    LBrace,
+    RBrace,
     Print,
+    Let,
+    If,
+    Else,
+    While,
+    Assign,
     Semi,
     EOF,
 }
@@ -54,7 +166,7 @@ impl Lexer {
         }
     }
 
-    fn integer(&mut self) -> i64 {
+    fn number(&mut self) -> Result<Token, CompileError> {
         let start = self.pos;
 
         while let Some(c) = self.current() {
@@ -65,18 +177,78 @@ impl Lexer {
             }
         }
 
-        self.input[start..self.pos]
-            .iter()
-            .collect::<String>()
-            .parse()
-            .unwrap()
+        let is_float = self.current() == Some('.')
+            && self
+                .input
+                .get(self.pos + 1)
+                .is_some_and(|c| c.is_ascii_digit());
+
+        if is_float {
+            self.advance();
+
+            while let Some(c) = self.current() {
+                if c.is_ascii_digit() {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let text: String = self.input[start..self.pos].iter().collect();
+        let span = Span {
+            start,
+            end: self.pos,
+        };
+
+        if is_float {
+            text.parse::<f64>()
+                .map(Token::Float)
+                .map_err(|_| CompileError::UnexpectedToken(
+                    "invalid floating-point literal".to_string(),
+                    span,
+                ))
+        } else {
+            text.parse::<i64>()
+                .map(Token::Int)
+                .map_err(|_| CompileError::UnexpectedToken(
+                    "invalid integer literal".to_string(),
+                    span,
+                ))
+        }
+    }
+
+    fn identifier(&mut self) -> Token {
+        let start = self.pos;
+
+        while let Some(c) = self.current() {
+            if c.is_alphanumeric() || c == '_' {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        let word: String = self.input[start..self.pos].iter().collect();
+
+        match word.as_str() {
+            "print" => Token::Print,
+            "let" => Token::Let,
+            "if" => Token::If,
+            "else" => Token::Else,
+            "while" => Token::While,
+            _ => Token::Ident(word),
+        }
     }
 
-    fn next_token(&mut self) -> Token {
+    fn next_token(&mut self) -> Result<(Token, Span), CompileError> {
         self.skip_whitespace();
+        let start = self.pos;
+
+        let token = match self.current() {
+            Some(c) if c.is_ascii_digit() => self.number()?,
 
-        match self.current() {
-            Some(c) if c.is_ascii_digit() => Token::Int(self.integer()),
+            Some(c) if c.is_alphabetic() || c == '_' => self.identifier(),
 
             Some('+') => {
                 self.advance();
@@ -98,6 +270,52 @@ impl Lexer {
                 Token::Div
             }
 
+            Some('<') => {
+                self.advance();
+                if self.current() == Some('=') {
+                    self.advance();
+                    Token::Le
+                } else {
+                    Token::Lt
+                }
+            }
+
+            Some('>') => {
+                self.advance();
+                if self.current() == Some('=') {
+                    self.advance();
+                    Token::Ge
+                } else {
+                    Token::Gt
+                }
+            }
+
+            Some('=') => {
+                self.advance();
+                if self.current() == Some('=') {
+                    self.advance();
+                    Token::Eq
+                } else {
+                    Token::Assign
+                }
+            }
+
+            Some('!') => {
+                self.advance();
+                if self.current() == Some('=') {
+                    self.advance();
+                    Token::Neq
+                } else {
+                    return Err(CompileError::UnexpectedChar(
+                        '!',
+                        Span {
+                            start,
+                            end: self.pos,
+                        },
+                    ));
+                }
+            }
+
             Some('(') => {
                 self.advance();
                 Token::LParen
@@ -108,43 +326,83 @@ impl Lexer {
                 Token::RParen
             }
 
+            Some('{') => {
+                self.advance();
+                Token::LBrace
+            }
+
+            Some('}') => {
+                self.advance();
+                Token::RBrace
+            }
+
             Some(';') => {
                 self.advance();
                 Token::Semi
             }
 
-            Some('p') => {
-                let remaining: String =
-                    self.input[self.pos..].iter().collect();
+            None => Token::EOF,
 
-                if remaining.starts_with("print") {
-                    self.pos += 5;
-                    Token::Print
-                } else {
-                    panic!("Unexpected token");
-                }
+            Some(c) => {
+                self.advance();
+                return Err(CompileError::UnexpectedChar(
+                    c,
+                    Span {
+                        start,
+                        end: self.pos,
+                    },
+                ));
             }
+        };
+
+        Ok((
+            token,
+            Span {
+                start,
+                end: self.pos,
+            },
+        ))
+    }
+}
 
-            None => Token::EOF,
+fn lex_all(lexer: &mut Lexer) -> Result<Vec<(Token, Span)>, CompileError> {
+    let mut tokens = Vec::new();
+
+    loop {
+        let (token, span) = lexer.next_token()?;
+        let is_eof = token == Token::EOF;
+        tokens.push((token, span));
 
-            _ => panic!("Invalid character"),
+        if is_eof {
+            break;
         }
     }
+
+    Ok(tokens)
 }
 
 // ======================
 // AST
 // ======================
 
+#[derive(Debug)]
 enum Expr {
     Num(i64),
-    BinOp(Box<Expr>, Token, Box<Expr>),
+    FloatNum(f64),
+    Var(String, Span),
+    BinOp(Box<Expr>, Token, Box<Expr>, Span),
 }
 
+#[derive(Debug)]
 enum Stmt {
     Print(Expr),
+    Let(String, Expr),
+    Assign(String, Expr, Span),
+    If(Expr, Vec<Stmt>, Option<Vec<Stmt>>),
+    While(Expr, Vec<Stmt>),
 }
 
+#[derive(Debug)]
 struct Program {
     stmts: Vec<Stmt>,
 }
@@ -156,62 +414,91 @@ struct Program {
 struct Parser {
     lexer: Lexer,
     current: Token,
+    current_span: Span,
 }
 
 impl Parser {
-    fn new(mut lexer: Lexer) -> Self {
-        let current = lexer.next_token();
-        Self { lexer, current }
+    fn new(mut lexer: Lexer) -> Result<Self, CompileError> {
+        let (current, current_span) = lexer.next_token()?;
+        Ok(Self {
+            lexer,
+            current,
+            current_span,
+        })
     }
 
-    fn eat(&mut self, expected: Token) {
+    fn eat(&mut self, expected: Token) -> Result<(), CompileError> {
         if std::mem::discriminant(&self.current)
             == std::mem::discriminant(&expected)
         {
-            self.current = self.lexer.next_token();
+            let (token, span) = self.lexer.next_token()?;
+            self.current = token;
+            self.current_span = span;
+            Ok(())
         } else {
-            panic!("Unexpected token");
+            Err(CompileError::ExpectedToken(
+                format!("{:?}", expected),
+                self.current_span,
+            ))
         }
     }
 
-    fn factor(&mut self) -> Expr {
+    fn factor(&mut self) -> Result<Expr, CompileError> {
         match self.current.clone() {
             Token::Int(n) => {
-                self.eat(Token::Int(0));
-                Expr::Num(n)
+                self.eat(Token::Int(0))?;
+                Ok(Expr::Num(n))
+            }
+
+            Token::Float(f) => {
+                self.eat(Token::Float(0.0))?;
+                Ok(Expr::FloatNum(f))
+            }
+
+            Token::Ident(name) => {
+                let span = self.current_span;
+                self.eat(Token::Ident(String::new()))?;
+                Ok(Expr::Var(name, span))
             }
 
             Token::LParen => {
-                self.eat(Token::LParen);
-                let expr = self.expr();
-                self.eat(Token::RParen);
-                expr
+                self.eat(Token::LParen)?;
+                let expr = self.expr()?;
+                self.eat(Token::RParen)?;
+                Ok(expr)
             }
 
-            _ => panic!("Expected number"),
+            _ => Err(CompileError::UnexpectedToken(
+                "expected a number, identifier, or '('".to_string(),
+                self.current_span,
+            )),
         }
     }
 
-    fn term(&mut self) -> Expr {
-        let mut node = self.factor();
+    fn term(&mut self) -> Result<Expr, CompileError> {
+        let mut node = self.factor()?;
 
         loop {
             match self.current {
                 Token::Mul => {
-                    self.eat(Token::Mul);
+                    let op_span = self.current_span;
+                    self.eat(Token::Mul)?;
                     node = Expr::BinOp(
                         Box::new(node),
                         Token::Mul,
-                        Box::new(self.factor()),
+                        Box::new(self.factor()?),
+                        op_span,
                     );
                 }
 
                 Token::Div => {
-                    self.eat(Token::Div);
+                    let op_span = self.current_span;
+                    self.eat(Token::Div)?;
                     node = Expr::BinOp(
                         Box::new(node),
                         Token::Div,
-                        Box::new(self.factor()),
+                        Box::new(self.factor()?),
+                        op_span,
                     );
                 }
 
@@ -219,29 +506,33 @@ impl Parser {
             }
         }
 
-        node
+        Ok(node)
     }
 
-    fn expr(&mut self) -> Expr {
-        let mut node = self.term();
+    fn expr(&mut self) -> Result<Expr, CompileError> {
+        let mut node = self.term()?;
 
         loop {
             match self.current {
                 Token::Plus => {
-                    self.eat(Token::Plus);
+                    let op_span = self.current_span;
+                    self.eat(Token::Plus)?;
                     node = Expr::BinOp(
                         Box::new(node),
                         Token::Plus,
-                        Box::new(self.term()),
+                        Box::new(self.term()?),
+                        op_span,
                     );
                 }
 
                 Token::Minus => {
-                    self.eat(Token::Minus);
+                    let op_span = self.current_span;
+                    self.eat(Token::Minus)?;
                     node = Expr::BinOp(
                         Box::new(node),
                         Token::Minus,
-                        Box::new(self.term()),
+                        Box::new(self.term()?),
+                        op_span,
                     );
                 }
 
@@ -249,24 +540,242 @@ impl Parser {
             }
         }
 
-        node
+        Ok(node)
     }
 
-    fn statement(&mut self) -> Stmt {
-        self.eat(Token::Print);
-        let expr = self.expr();
-        self.eat(Token::Semi);
-        Stmt::Print(expr)
+    fn comparison(&mut self) -> Result<Expr, CompileError> {
+        let mut node = self.expr()?;
+
+        while let Token::Lt
+        | Token::Gt
+        | Token::Eq
+        | Token::Neq
+        | Token::Le
+        | Token::Ge = self.current
+        {
+            let op = self.current.clone();
+            let op_span = self.current_span;
+            self.eat(op.clone())?;
+            node = Expr::BinOp(Box::new(node), op, Box::new(self.expr()?), op_span);
+        }
+
+        Ok(node)
     }
 
-    fn program(&mut self) -> Program {
+    fn block(&mut self) -> Result<Vec<Stmt>, CompileError> {
+        self.eat(Token::LBrace)?;
+
+        let mut stmts = Vec::new();
+
+        while self.current != Token::RBrace {
+            stmts.push(self.statement()?);
+        }
+
+        self.eat(Token::RBrace)?;
+
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, CompileError> {
+        match self.current.clone() {
+            Token::Print => {
+                self.eat(Token::Print)?;
+                let expr = self.expr()?;
+                self.eat(Token::Semi)?;
+                Ok(Stmt::Print(expr))
+            }
+
+            Token::Let => {
+                self.eat(Token::Let)?;
+                let name = match self.current.clone() {
+                    Token::Ident(name) => {
+                        self.eat(Token::Ident(String::new()))?;
+                        name
+                    }
+                    _ => {
+                        return Err(CompileError::UnexpectedToken(
+                            "expected an identifier".to_string(),
+                            self.current_span,
+                        ))
+                    }
+                };
+                self.eat(Token::Assign)?;
+                let expr = self.expr()?;
+                self.eat(Token::Semi)?;
+                Ok(Stmt::Let(name, expr))
+            }
+
+            Token::If => {
+                self.eat(Token::If)?;
+                self.eat(Token::LParen)?;
+                let cond = self.comparison()?;
+                self.eat(Token::RParen)?;
+                let then_block = self.block()?;
+
+                let else_block = if self.current == Token::Else {
+                    self.eat(Token::Else)?;
+                    Some(self.block()?)
+                } else {
+                    None
+                };
+
+                Ok(Stmt::If(cond, then_block, else_block))
+            }
+
+            Token::While => {
+                self.eat(Token::While)?;
+                self.eat(Token::LParen)?;
+                let cond = self.comparison()?;
+                self.eat(Token::RParen)?;
+                let body = self.block()?;
+                Ok(Stmt::While(cond, body))
+            }
+
+            Token::Ident(name) => {
+                let span = self.current_span;
+                self.eat(Token::Ident(String::new()))?;
+                self.eat(Token::Assign)?;
+                let expr = self.expr()?;
+                self.eat(Token::Semi)?;
+                Ok(Stmt::Assign(name, expr, span))
+            }
+
+            _ => Err(CompileError::UnexpectedToken(
+                "expected a statement".to_string(),
+                self.current_span,
+            )),
+        }
+    }
+
+    fn program(&mut self) -> Result<Program, CompileError> {
         let mut stmts = Vec::new();
 
         while self.current != Token::EOF {
-            stmts.push(self.statement());
+            stmts.push(self.statement()?);
         }
 
-        Program { stmts }
+        Ok(Program { stmts })
+    }
+}
+
+// ======================
+// OPTIMIZER
+// ======================
+
+fn as_f64(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Num(n) => Some(*n as f64),
+        Expr::FloatNum(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn fold_binop(left: Expr, op: Token, right: Expr, span: Span) -> Result<Expr, CompileError> {
+    if let (Expr::Num(a), Expr::Num(b)) = (&left, &right) {
+        let (a, b) = (*a, *b);
+
+        match op {
+            Token::Plus => return Ok(Expr::Num(a + b)),
+            Token::Minus => return Ok(Expr::Num(a - b)),
+            Token::Mul => return Ok(Expr::Num(a * b)),
+
+            Token::Div => {
+                if b == 0 {
+                    return Err(CompileError::UnexpectedToken(
+                        "division by zero in constant expression".to_string(),
+                        span,
+                    ));
+                }
+                return Ok(Expr::Num(a / b));
+            }
+
+            Token::Lt => return Ok(Expr::Num((a < b) as i64)),
+            Token::Gt => return Ok(Expr::Num((a > b) as i64)),
+            Token::Eq => return Ok(Expr::Num((a == b) as i64)),
+            Token::Neq => return Ok(Expr::Num((a != b) as i64)),
+            Token::Le => return Ok(Expr::Num((a <= b) as i64)),
+            Token::Ge => return Ok(Expr::Num((a >= b) as i64)),
+
+            _ => {}
+        }
+    } else if let (Some(a), Some(b)) = (as_f64(&left), as_f64(&right)) {
+        match op {
+            Token::Plus => return Ok(Expr::FloatNum(a + b)),
+            Token::Minus => return Ok(Expr::FloatNum(a - b)),
+            Token::Mul => return Ok(Expr::FloatNum(a * b)),
+
+            Token::Div => {
+                if b == 0.0 {
+                    return Err(CompileError::UnexpectedToken(
+                        "division by zero in constant expression".to_string(),
+                        span,
+                    ));
+                }
+                return Ok(Expr::FloatNum(a / b));
+            }
+
+            Token::Lt => return Ok(Expr::Num((a < b) as i64)),
+            Token::Gt => return Ok(Expr::Num((a > b) as i64)),
+            Token::Eq => return Ok(Expr::Num((a == b) as i64)),
+            Token::Neq => return Ok(Expr::Num((a != b) as i64)),
+            Token::Le => return Ok(Expr::Num((a <= b) as i64)),
+            Token::Ge => return Ok(Expr::Num((a >= b) as i64)),
+
+            _ => {}
+        }
+    }
+
+    match (&left, &op, &right) {
+        (_, Token::Plus, Expr::Num(0)) => return Ok(left),
+        (Expr::Num(0), Token::Plus, _) => return Ok(right),
+        (_, Token::Minus, Expr::Num(0)) => return Ok(left),
+        (_, Token::Mul, Expr::Num(1)) => return Ok(left),
+        (Expr::Num(1), Token::Mul, _) => return Ok(right),
+        (_, Token::Mul, Expr::Num(0)) => return Ok(Expr::Num(0)),
+        (Expr::Num(0), Token::Mul, _) => return Ok(Expr::Num(0)),
+        _ => {}
+    }
+
+    Ok(Expr::BinOp(Box::new(left), op, Box::new(right), span))
+}
+
+fn optimize(expr: Expr) -> Result<Expr, CompileError> {
+    match expr {
+        Expr::BinOp(left, op, right, span) => {
+            let left = optimize(*left)?;
+            let right = optimize(*right)?;
+            fold_binop(left, op, right, span)
+        }
+
+        other => Ok(other),
+    }
+}
+
+fn optimize_stmts(stmts: Vec<Stmt>) -> Result<Vec<Stmt>, CompileError> {
+    stmts.into_iter().map(optimize_stmt).collect()
+}
+
+fn optimize_stmt(stmt: Stmt) -> Result<Stmt, CompileError> {
+    match stmt {
+        Stmt::Print(expr) => Ok(Stmt::Print(optimize(expr)?)),
+        Stmt::Let(name, expr) => Ok(Stmt::Let(name, optimize(expr)?)),
+        Stmt::Assign(name, expr, span) => Ok(Stmt::Assign(name, optimize(expr)?, span)),
+
+        Stmt::If(cond, then_block, else_block) => {
+            let cond = optimize(cond)?;
+            let then_block = optimize_stmts(then_block)?;
+            let else_block = match else_block {
+                Some(stmts) => Some(optimize_stmts(stmts)?),
+                None => None,
+            };
+            Ok(Stmt::If(cond, then_block, else_block))
+        }
+
+        Stmt::While(cond, body) => {
+            let cond = optimize(cond)?;
+            let body = optimize_stmts(body)?;
+            Ok(Stmt::While(cond, body))
+        }
     }
 }
 
@@ -276,12 +785,18 @@ impl Parser {
 
 struct CodeGen {
     output: String,
+    vars: HashMap<String, usize>,
+    label_count: usize,
+    floats: Vec<f64>,
 }
 
 impl CodeGen {
     fn new() -> Self {
         Self {
             output: String::new(),
+            vars: HashMap::new(),
+            label_count: 0,
+            floats: Vec::new(),
         }
     }
 
@@ -290,60 +805,326 @@ impl CodeGen {
         self.output.push('\n');
     }
 
-    fn gen_expr(&mut self, expr: &Expr) {
+    fn new_label(&mut self) -> String {
+        let label = format!(".L{}", self.label_count);
+        self.label_count += 1;
+        label
+    }
+
+    fn float_label(&mut self, value: f64) -> String {
+        let label = format!("flt{}", self.floats.len());
+        self.floats.push(value);
+        label
+    }
+
+    fn offset_of(&self, name: &str, span: Span) -> Result<usize, CompileError> {
+        self.vars
+            .get(name)
+            .copied()
+            .ok_or_else(|| CompileError::undeclared_variable(name, span))
+    }
+
+    fn collect_vars(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Let(name, _) if !self.vars.contains_key(name) => {
+                    let offset = (self.vars.len() + 1) * 8;
+                    self.vars.insert(name.clone(), offset);
+                }
+
+                Stmt::If(_, then_block, else_block) => {
+                    self.collect_vars(then_block);
+                    if let Some(else_block) = else_block {
+                        self.collect_vars(else_block);
+                    }
+                }
+
+                Stmt::While(_, body) => {
+                    self.collect_vars(body);
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    // Returns whether the result was left in xmm0 (float) rather than rax (int).
+    fn gen_expr(&mut self, expr: &Expr) -> Result<bool, CompileError> {
         match expr {
             Expr::Num(n) => {
                 self.emit(&format!("    mov rax, {}", n));
+                Ok(false)
             }
 
-            Expr::BinOp(left, op, right) => {
-                self.gen_expr(left);
-                self.emit("    push rax");
+            Expr::FloatNum(f) => {
+                let label = self.float_label(*f);
+                self.emit(&format!("    movsd xmm0, [{}]", label));
+                Ok(true)
+            }
+
+            Expr::Var(name, span) => {
+                let offset = self.offset_of(name, *span)?;
+                self.emit(&format!("    mov rax, [rbp-{}]", offset));
+                Ok(false)
+            }
 
-                self.gen_expr(right);
-                self.emit("    pop rbx");
+            Expr::BinOp(left, op, right, _span) => {
+                let left_is_float = self.gen_expr(left)?;
+                if left_is_float {
+                    self.emit("    sub rsp, 8");
+                    self.emit("    movsd [rsp], xmm0");
+                } else {
+                    self.emit("    push rax");
+                }
 
-                match op {
-                    Token::Plus =>
-                        self.emit("    add rax, rbx"),
+                let right_is_float = self.gen_expr(right)?;
 
-                    Token::Minus => {
-                        self.emit("    sub rbx, rax");
-                        self.emit("    mov rax, rbx");
+                let result = if left_is_float || right_is_float {
+                    if right_is_float {
+                        self.emit("    movsd xmm1, xmm0");
+                    } else {
+                        self.emit("    cvtsi2sd xmm1, rax");
                     }
 
-                    Token::Mul =>
-                        self.emit("    imul rax, rbx"),
+                    if left_is_float {
+                        self.emit("    movsd xmm0, [rsp]");
+                        self.emit("    add rsp, 8");
+                    } else {
+                        self.emit("    pop rax");
+                        self.emit("    cvtsi2sd xmm0, rax");
+                    }
 
-                    Token::Div => {
-                        self.emit("    mov rdx, 0");
-                        self.emit("    mov rcx, rax");
-                        self.emit("    mov rax, rbx");
-                        self.emit("    idiv rcx");
+                    match op {
+                        Token::Plus => {
+                            self.emit("    addsd xmm0, xmm1");
+                            true
+                        }
+
+                        Token::Minus => {
+                            self.emit("    subsd xmm0, xmm1");
+                            true
+                        }
+
+                        Token::Mul => {
+                            self.emit("    mulsd xmm0, xmm1");
+                            true
+                        }
+
+                        Token::Div => {
+                            self.emit("    divsd xmm0, xmm1");
+                            true
+                        }
+
+                        Token::Lt => {
+                            self.emit("    comisd xmm0, xmm1");
+                            self.emit("    setb al");
+                            self.emit("    movzx rax, al");
+                            false
+                        }
+
+                        Token::Gt => {
+                            self.emit("    comisd xmm0, xmm1");
+                            self.emit("    seta al");
+                            self.emit("    movzx rax, al");
+                            false
+                        }
+
+                        Token::Eq => {
+                            self.emit("    comisd xmm0, xmm1");
+                            self.emit("    sete al");
+                            self.emit("    movzx rax, al");
+                            false
+                        }
+
+                        Token::Neq => {
+                            self.emit("    comisd xmm0, xmm1");
+                            self.emit("    setne al");
+                            self.emit("    movzx rax, al");
+                            false
+                        }
+
+                        Token::Le => {
+                            self.emit("    comisd xmm0, xmm1");
+                            self.emit("    setbe al");
+                            self.emit("    movzx rax, al");
+                            false
+                        }
+
+                        Token::Ge => {
+                            self.emit("    comisd xmm0, xmm1");
+                            self.emit("    setae al");
+                            self.emit("    movzx rax, al");
+                            false
+                        }
+
+                        _ => false,
+                    }
+                } else {
+                    self.emit("    pop rbx");
+
+                    match op {
+                        Token::Plus =>
+                            self.emit("    add rax, rbx"),
+
+                        Token::Minus => {
+                            self.emit("    sub rbx, rax");
+                            self.emit("    mov rax, rbx");
+                        }
+
+                        Token::Mul =>
+                            self.emit("    imul rax, rbx"),
+
+                        Token::Div => {
+                            self.emit("    mov rdx, 0");
+                            self.emit("    mov rcx, rax");
+                            self.emit("    mov rax, rbx");
+                            self.emit("    idiv rcx");
+                        }
+
+                        Token::Lt => {
+                            self.emit("    cmp rbx, rax");
+                            self.emit("    setl al");
+                            self.emit("    movzx rax, al");
+                        }
+
+                        Token::Gt => {
+                            self.emit("    cmp rbx, rax");
+                            self.emit("    setg al");
+                            self.emit("    movzx rax, al");
+                        }
+
+                        Token::Eq => {
+                            self.emit("    cmp rbx, rax");
+                            self.emit("    sete al");
+                            self.emit("    movzx rax, al");
+                        }
+
+                        Token::Neq => {
+                            self.emit("    cmp rbx, rax");
+                            self.emit("    setne al");
+                            self.emit("    movzx rax, al");
+                        }
+
+                        Token::Le => {
+                            self.emit("    cmp rbx, rax");
+                            self.emit("    setle al");
+                            self.emit("    movzx rax, al");
+                        }
+
+                        Token::Ge => {
+                            self.emit("    cmp rbx, rax");
+                            self.emit("    setge al");
+                            self.emit("    movzx rax, al");
+                        }
+
+                        _ => {}
                     }
 
-                    _ => {}
-                }
+                    false
+                };
+
+                Ok(result)
             }
         }
     }
 
-    fn gen_stmt(&mut self, stmt: &Stmt) {
+    fn gen_stmt(&mut self, stmt: &Stmt) -> Result<(), CompileError> {
         match stmt {
             Stmt::Print(expr) => {
-                self.gen_expr(expr);
-                self.emit("    mov rdi, rax");
-                self.emit("    call print_int");
+                if self.gen_expr(expr)? {
+                    self.emit("    call print_float");
+                } else {
+                    self.emit("    mov rdi, rax");
+                    self.emit("    call print_int");
+                }
+            }
+
+            Stmt::Let(name, expr) => {
+                if self.gen_expr(expr)? {
+                    return Err(CompileError::float_variable(name, Span { start: 0, end: 0 }));
+                }
+                // collect_vars already registered every Let name, so this can't fail.
+                let offset = self.offset_of(name, Span { start: 0, end: 0 })?;
+                self.emit(&format!("    mov [rbp-{}], rax", offset));
+            }
+
+            Stmt::Assign(name, expr, span) => {
+                if self.gen_expr(expr)? {
+                    return Err(CompileError::float_variable(name, *span));
+                }
+                let offset = self.offset_of(name, *span)?;
+                self.emit(&format!("    mov [rbp-{}], rax", offset));
+            }
+
+            Stmt::If(cond, then_block, else_block) => {
+                if self.gen_expr(cond)? {
+                    self.emit("    cvttsd2si rax, xmm0");
+                }
+                self.emit("    test rax, rax");
+
+                match else_block {
+                    Some(else_stmts) => {
+                        let else_label = self.new_label();
+                        let end_label = self.new_label();
+
+                        self.emit(&format!("    jz {}", else_label));
+                        for stmt in then_block {
+                            self.gen_stmt(stmt)?;
+                        }
+                        self.emit(&format!("    jmp {}", end_label));
+
+                        self.emit(&format!("{}:", else_label));
+                        for stmt in else_stmts {
+                            self.gen_stmt(stmt)?;
+                        }
+
+                        self.emit(&format!("{}:", end_label));
+                    }
+
+                    None => {
+                        let end_label = self.new_label();
+
+                        self.emit(&format!("    jz {}", end_label));
+                        for stmt in then_block {
+                            self.gen_stmt(stmt)?;
+                        }
+
+                        self.emit(&format!("{}:", end_label));
+                    }
+                }
+            }
+
+            Stmt::While(cond, body) => {
+                let top_label = self.new_label();
+                let end_label = self.new_label();
+
+                self.emit(&format!("{}:", top_label));
+                if self.gen_expr(cond)? {
+                    self.emit("    cvttsd2si rax, xmm0");
+                }
+                self.emit("    test rax, rax");
+                self.emit(&format!("    jz {}", end_label));
+
+                for stmt in body {
+                    self.gen_stmt(stmt)?;
+                }
+
+                self.emit(&format!("    jmp {}", top_label));
+                self.emit(&format!("{}:", end_label));
             }
         }
+
+        Ok(())
     }
 
-    fn generate(mut self, program: Program) -> String {
+    fn generate(mut self, program: Program) -> Result<String, CompileError> {
+        self.collect_vars(&program.stmts);
+        let frame_size = self.vars.len() * 8;
 
         self.emit("global _start");
         self.emit("section .text");
 
-        self.emit("print_int:");
+        self.emit("print_digits:");
         self.emit("    mov rcx, buffer+20");
         self.emit("    mov rbx, 10");
         self.emit("    mov rax, rdi");
@@ -364,6 +1145,84 @@ impl CodeGen {
         self.emit("    sub rdx, rcx");
         self.emit("    syscall");
 
+        self.emit("    ret");
+
+        self.emit("print_int:");
+        self.emit("    call print_digits");
+        self.emit("    mov rax, 1");
+        self.emit("    mov rdi, 1");
+        self.emit("    mov rsi, newline");
+        self.emit("    mov rdx, 1");
+        self.emit("    syscall");
+        self.emit("    ret");
+
+        // Like print_digits, but always writes exactly 6 digits, padding
+        // with leading zeros, so the fractional part of a float isn't
+        // shortened (e.g. 50000 printed as "050000", not "50000").
+        self.emit("print_digits_padded:");
+        self.emit("    mov rcx, buffer+20");
+        self.emit("    mov rbx, 10");
+        self.emit("    mov rax, rdi");
+        self.emit("    mov r8, 6");
+
+        self.emit("pad_convert:");
+        self.emit("    xor rdx, rdx");
+        self.emit("    div rbx");
+        self.emit("    add dl, '0'");
+        self.emit("    dec rcx");
+        self.emit("    mov [rcx], dl");
+        self.emit("    dec r8");
+        self.emit("    jnz pad_convert");
+
+        self.emit("    mov rax, 1");
+        self.emit("    mov rdi, 1");
+        self.emit("    mov rsi, rcx");
+        self.emit("    mov rdx, buffer+20");
+        self.emit("    sub rdx, rcx");
+        self.emit("    syscall");
+
+        self.emit("    ret");
+
+        self.emit("print_float:");
+        self.emit("    pxor xmm2, xmm2");
+        self.emit("    comisd xmm0, xmm2");
+        self.emit("    jae float_sign_done");
+
+        self.emit("    push rax");
+        self.emit("    mov rax, 1");
+        self.emit("    mov rdi, 1");
+        self.emit("    mov rsi, minus");
+        self.emit("    mov rdx, 1");
+        self.emit("    syscall");
+        self.emit("    pop rax");
+
+        self.emit("    subsd xmm2, xmm0");
+        self.emit("    movsd xmm0, xmm2");
+
+        self.emit("float_sign_done:");
+        self.emit("    cvttsd2si rax, xmm0");
+        self.emit("    push rax");
+        self.emit("    mov rdi, rax");
+        self.emit("    call print_digits");
+        self.emit("    pop rax");
+
+        self.emit("    push rax");
+        self.emit("    mov rax, 1");
+        self.emit("    mov rdi, 1");
+        self.emit("    mov rsi, dot");
+        self.emit("    mov rdx, 1");
+        self.emit("    syscall");
+        self.emit("    pop rax");
+
+        self.emit("    cvtsi2sd xmm1, rax");
+        self.emit("    subsd xmm0, xmm1");
+        self.emit("    mov rax, 1000000");
+        self.emit("    cvtsi2sd xmm1, rax");
+        self.emit("    mulsd xmm0, xmm1");
+        self.emit("    cvttsd2si rax, xmm0");
+        self.emit("    mov rdi, rax");
+        self.emit("    call print_digits_padded");
+
         self.emit("    mov rax, 1");
         self.emit("    mov rdi, 1");
         self.emit("    mov rsi, newline");
@@ -373,9 +1232,14 @@ impl CodeGen {
         self.emit("    ret");
 
         self.emit("_start:");
+        self.emit("    push rbp");
+        self.emit("    mov rbp, rsp");
+        if frame_size > 0 {
+            self.emit(&format!("    sub rsp, {}", frame_size));
+        }
 
         for stmt in program.stmts {
-            self.gen_stmt(&stmt);
+            self.gen_stmt(&stmt)?;
         }
 
         self.emit("    mov rax, 60");
@@ -387,8 +1251,175 @@ impl CodeGen {
 
         self.emit("section .data");
         self.emit("newline db 10");
+        self.emit("dot db '.'");
+        self.emit("minus db '-'");
+
+        let floats = self.floats.clone();
+        for (i, value) in floats.iter().enumerate() {
+            self.emit(&format!("flt{} dq {:?}", i, value));
+        }
 
-        self.output
+        Ok(self.output)
+    }
+}
+
+// ======================
+// INTERPRETER
+// ======================
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+impl Value {
+    fn as_f64(&self) -> f64 {
+        match self {
+            Value::Int(n) => *n as f64,
+            Value::Float(f) => *f,
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Int(n) => *n != 0,
+            Value::Float(f) => *f != 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            // Uses the same fixed 6-digit fraction as the compiled backend's
+            // print_float; this rounds the last digit, print_float truncates it.
+            Value::Float(x) => write!(f, "{:.6}", x),
+        }
+    }
+}
+
+struct Interpreter {
+    vars: HashMap<String, Value>,
+}
+
+impl Interpreter {
+    fn new() -> Self {
+        Self {
+            vars: HashMap::new(),
+        }
+    }
+
+    fn eval_expr(&self, expr: &Expr) -> Result<Value, CompileError> {
+        match expr {
+            Expr::Num(n) => Ok(Value::Int(*n)),
+            Expr::FloatNum(f) => Ok(Value::Float(*f)),
+
+            Expr::Var(name, span) => self
+                .vars
+                .get(name)
+                .copied()
+                .ok_or_else(|| CompileError::undeclared_variable(name, *span)),
+
+            Expr::BinOp(left, op, right, span) => {
+                let l = self.eval_expr(left)?;
+                let r = self.eval_expr(right)?;
+
+                let value = if let (Value::Int(a), Value::Int(b)) = (l, r) {
+                    let n = match op {
+                        Token::Plus => a
+                            .checked_add(b)
+                            .ok_or_else(|| CompileError::arithmetic_overflow(*span))?,
+                        Token::Minus => a
+                            .checked_sub(b)
+                            .ok_or_else(|| CompileError::arithmetic_overflow(*span))?,
+                        Token::Mul => a
+                            .checked_mul(b)
+                            .ok_or_else(|| CompileError::arithmetic_overflow(*span))?,
+                        Token::Div => {
+                            if b == 0 {
+                                return Err(CompileError::division_by_zero(*span));
+                            }
+                            a.checked_div(b)
+                                .ok_or_else(|| CompileError::arithmetic_overflow(*span))?
+                        }
+                        Token::Lt => (a < b) as i64,
+                        Token::Gt => (a > b) as i64,
+                        Token::Eq => (a == b) as i64,
+                        Token::Neq => (a != b) as i64,
+                        Token::Le => (a <= b) as i64,
+                        Token::Ge => (a >= b) as i64,
+                        _ => panic!("Invalid operator"),
+                    };
+                    Value::Int(n)
+                } else {
+                    let a = l.as_f64();
+                    let b = r.as_f64();
+
+                    match op {
+                        Token::Plus => Value::Float(a + b),
+                        Token::Minus => Value::Float(a - b),
+                        Token::Mul => Value::Float(a * b),
+                        Token::Div => Value::Float(a / b),
+                        Token::Lt => Value::Int((a < b) as i64),
+                        Token::Gt => Value::Int((a > b) as i64),
+                        Token::Eq => Value::Int((a == b) as i64),
+                        Token::Neq => Value::Int((a != b) as i64),
+                        Token::Le => Value::Int((a <= b) as i64),
+                        Token::Ge => Value::Int((a >= b) as i64),
+                        _ => panic!("Invalid operator"),
+                    }
+                };
+
+                Ok(value)
+            }
+        }
+    }
+
+    fn exec_stmt(&mut self, stmt: &Stmt) -> Result<(), CompileError> {
+        match stmt {
+            Stmt::Print(expr) => {
+                println!("{}", self.eval_expr(expr)?);
+            }
+
+            Stmt::Let(name, expr) => {
+                let value = self.eval_expr(expr)?;
+                self.vars.insert(name.clone(), value);
+            }
+
+            Stmt::Assign(name, expr, span) => {
+                if !self.vars.contains_key(name) {
+                    return Err(CompileError::undeclared_variable(name, *span));
+                }
+                let value = self.eval_expr(expr)?;
+                self.vars.insert(name.clone(), value);
+            }
+
+            Stmt::If(cond, then_block, else_block) => {
+                if self.eval_expr(cond)?.is_truthy() {
+                    self.run(then_block)?;
+                } else if let Some(else_block) = else_block {
+                    self.run(else_block)?;
+                }
+            }
+
+            Stmt::While(cond, body) => {
+                while self.eval_expr(cond)?.is_truthy() {
+                    self.run(body)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run(&mut self, stmts: &[Stmt]) -> Result<(), CompileError> {
+        for stmt in stmts {
+            self.exec_stmt(stmt)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -400,19 +1431,95 @@ fn main() {
 
     let args: Vec<String> = env::args().collect();
 
+    let usage = "Usage: compiler [--run|-r] [-O|--optimize] [--dump-tokens] [--dump-ast] <file>";
+
     if args.len() < 2 {
-        println!("Usage: compiler <file>");
+        println!("{}", usage);
         return;
     }
 
-    let source = fs::read_to_string(&args[1]).unwrap();
+    let is_flag = |a: &str| {
+        matches!(
+            a,
+            "--run" | "-r" | "-O" | "--optimize" | "--dump-tokens" | "--dump-ast"
+        )
+    };
+
+    let run_mode = args[1..].iter().any(|a| a == "--run" || a == "-r");
+    let optimize_mode = args[1..]
+        .iter()
+        .any(|a| a == "-O" || a == "--optimize");
+    let dump_tokens = args[1..].iter().any(|a| a == "--dump-tokens");
+    let dump_ast = args[1..].iter().any(|a| a == "--dump-ast");
+
+    let file = match args[1..].iter().find(|a| !is_flag(a)) {
+        Some(file) => file,
+        None => {
+            println!("{}", usage);
+            return;
+        }
+    };
 
-    let lexer = Lexer::new(source);
-    let mut parser = Parser::new(lexer);
+    let source = fs::read_to_string(file).unwrap();
 
-    let program = parser.program();
+    if dump_tokens {
+        let mut lexer = Lexer::new(source.clone());
+        match lex_all(&mut lexer) {
+            Ok(tokens) => {
+                for (token, span) in tokens {
+                    println!("{:?} @ {}..{}", token, span.start, span.end);
+                }
+            }
+            Err(err) => {
+                report_error(&source, &err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let lexer = Lexer::new(source.clone());
+
+    let program = match Parser::new(lexer).and_then(|mut parser| parser.program()) {
+        Ok(program) => program,
+        Err(err) => {
+            report_error(&source, &err);
+            std::process::exit(1);
+        }
+    };
 
-    let asm = CodeGen::new().generate(program);
+    if dump_ast {
+        println!("{:#?}", program);
+        return;
+    }
+
+    let program = if optimize_mode {
+        match optimize_stmts(program.stmts) {
+            Ok(stmts) => Program { stmts },
+            Err(err) => {
+                report_error(&source, &err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        program
+    };
+
+    if run_mode {
+        if let Err(err) = Interpreter::new().run(&program.stmts) {
+            report_error(&source, &err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let asm = match CodeGen::new().generate(program) {
+        Ok(asm) => asm,
+        Err(err) => {
+            report_error(&source, &err);
+            std::process::exit(1);
+        }
+    };
 
     fs::write("out.asm", asm).unwrap();
 